@@ -0,0 +1,11 @@
+/// A single flashcard, identified by a stable content hash.
+///
+/// The hash is the card's primary key everywhere it is persisted; scheduling
+/// state (stability, difficulty, due date, ...) lives in the `cards` table
+/// keyed on `card_hash` rather than on this struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub card_hash: String,
+    pub front: String,
+    pub back: String,
+}
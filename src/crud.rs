@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
 use futures::TryStreamExt;
 use sqlx::Row;
@@ -13,17 +14,25 @@ use std::str::FromStr;
 use anyhow::anyhow;
 
 use crate::card::Card;
+use crate::fsrs::{schedule, FsrsParams, SchedulerState};
+use crate::migrations::run_migrations;
+use crate::review_log::ReviewLog;
+use crate::sync::{
+    decrypt_record, encrypt_record, load_or_create_host_id, load_or_create_sync_key,
+    RemoteClient, SyncStats,
+};
 
 pub struct DB {
     pool: SqlitePool,
+    data_dir: PathBuf,
 }
 
 impl DB {
     pub async fn new() -> Result<Self> {
         let proj_dirs = ProjectDirs::from("", "", "repeat")
             .ok_or_else(|| anyhow!("Could not determine project directory"))?;
-        let data_dir = proj_dirs.data_dir();
-        std::fs::create_dir_all(data_dir)
+        let data_dir = proj_dirs.data_dir().to_path_buf();
+        std::fs::create_dir_all(&data_dir)
             .map_err(|e| anyhow!("Failed to create data directory: {}", e))?;
 
         let db_path: PathBuf = data_dir.join("cards.db");
@@ -33,14 +42,9 @@ impl DB {
             .max_connections(5)
             .connect_with(options)
             .await?;
-        let table_exists = probe_schema_exists(&pool).await;
-        if let Ok(false) = table_exists {
-            sqlx::query(include_str!("schema.sql"))
-                .execute(&pool)
-                .await?;
-        }
+        run_migrations(&pool).await?;
 
-        Ok(Self { pool })
+        Ok(Self { pool, data_dir })
     }
 
     pub async fn add_card(&self, card: &Card) -> Result<()> {
@@ -79,7 +83,15 @@ impl DB {
         let now = chrono::Utc::now().to_rfc3339();
 
         for card in cards {
-            if self.card_exists(card).await? {
+            // Check existence against the open transaction rather than
+            // calling `card_exists` (which would acquire a second
+            // connection from `self.pool` and deadlock a pool sized to a
+            // single connection).
+            let (count,): (i64,) = sqlx::query_as("SELECT COUNT(1) FROM cards WHERE card_hash = ?")
+                .bind(&card.card_hash)
+                .fetch_one(&mut *tx)
+                .await?;
+            if count > 0 {
                 continue;
             }
 
@@ -118,30 +130,46 @@ impl DB {
         Ok(count > 0)
     }
 
+    /// Returns the due cards among `card_hashes` (a deck or tag's member
+    /// hashes), oldest-due first, capped at `card_limit`.
+    ///
+    /// SQLite does the due-date filtering and ordering; rows stream back
+    /// oldest-due first via `TryStreamExt` and we stop as soon as
+    /// `card_limit` wanted hashes have matched, instead of ever
+    /// materializing the full set of due rows in memory. We deliberately
+    /// don't bind one placeholder per hash here (`card_hash IN (?, ?, ...)`)
+    /// since that would hit SQLite's bound-parameter limit (999 by
+    /// default) for a large deck.
     pub async fn due_today(
         &self,
-        card_hashes: HashMap<String, Card>,
+        card_hashes: &HashMap<String, Card>,
         card_limit: Option<usize>,
     ) -> Result<Vec<Card>> {
-        let today = chrono::Local::now().date_naive();
+        if card_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // `due_date` is written as a UTC calendar date (see
+        // `record_review_with_params`), so compare against UTC here too —
+        // mixing UTC and local "today" would make a card's due-ness depend
+        // on the caller's timezone near local midnight.
+        let today = Utc::now().date_naive();
 
-        let sql = "SELECT card_hash 
-           FROM cards
-           WHERE due_date <= ? OR due_date IS NULL;";
+        let sql = "SELECT card_hash FROM cards \
+                   WHERE due_date <= ? OR due_date IS NULL \
+                   ORDER BY due_date ASC";
         let mut rows = sqlx::query(sql).bind(today).fetch(&self.pool);
+
         let mut cards = Vec::new();
         while let Some(row) = rows.try_next().await? {
             let card_hash: String = row.get("card_hash");
-            if !card_hashes.contains_key(&card_hash) {
+            let Some(card) = card_hashes.get(&card_hash) else {
                 continue;
-            }
-
-            if let Some(card) = card_hashes.get(&card_hash) {
-                cards.push(card.clone());
-            }
+            };
+            cards.push(card.clone());
 
-            if let Some(card_limit) = card_limit {
-                if cards.len() >= card_limit {
+            if let Some(limit) = card_limit {
+                if cards.len() >= limit {
                     break;
                 }
             }
@@ -149,11 +177,596 @@ impl DB {
 
         Ok(cards)
     }
+
+    /// Returns the number of cards due for review, without fetching any
+    /// rows, so a UI can cheaply show "N cards due".
+    pub async fn due_count(&self) -> Result<i64> {
+        let today = Utc::now().date_naive();
+
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(1) FROM cards WHERE due_date <= ? OR due_date IS NULL")
+                .bind(today)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(count)
+    }
+
+    /// Applies an FSRS scheduling update for `card` and persists the new
+    /// stability, difficulty, and due date in one transaction.
+    ///
+    /// `grade` must be in `1..=4` (Again/Hard/Good/Easy). Uses the active
+    /// FSRS weights and target retention from the `kv` store (falling back
+    /// to the built-in defaults), see `active_fsrs_params`; use
+    /// `record_review_with_params` to override them for a single call.
+    pub async fn record_review(
+        &self,
+        card: &Card,
+        grade: u8,
+        reviewed_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let params = self.active_fsrs_params().await?;
+        self.record_review_with_params(card, grade, reviewed_at, &params)
+            .await
+    }
+
+    /// Same as `record_review`, but with an explicit set of FSRS parameters.
+    pub async fn record_review_with_params(
+        &self,
+        card: &Card,
+        grade: u8,
+        reviewed_at: DateTime<Utc>,
+        params: &FsrsParams,
+    ) -> Result<()> {
+        if !(1..=4).contains(&grade) {
+            return Err(anyhow!("grade must be in 1..=4, got {}", grade));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            "SELECT stability, difficulty, last_reviewed_at FROM cards WHERE card_hash = ?",
+        )
+        .bind(&card.card_hash)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow!("card {} not found", card.card_hash))?;
+
+        let stability: Option<f64> = row.get("stability");
+        let difficulty: Option<f64> = row.get("difficulty");
+        let last_reviewed_at: Option<String> = row.get("last_reviewed_at");
+
+        let prior = match (stability, difficulty) {
+            (Some(stability), Some(difficulty)) => Some(SchedulerState {
+                stability,
+                difficulty,
+            }),
+            _ => None,
+        };
+
+        let elapsed_days = match &last_reviewed_at {
+            Some(last) => {
+                let last = DateTime::parse_from_rfc3339(last)?.with_timezone(&Utc);
+                (reviewed_at - last).num_seconds() as f64 / 86_400.0
+            }
+            None => 0.0,
+        };
+
+        let result = schedule(prior, grade, elapsed_days, params)?;
+        let due_date = (reviewed_at + chrono::Duration::days(result.interval_days)).date_naive();
+
+        sqlx::query(
+            r#"
+            UPDATE cards
+            SET stability = ?,
+                difficulty = ?,
+                interval_raw = ?,
+                interval_days = ?,
+                due_date = ?,
+                last_reviewed_at = ?,
+                review_count = review_count + 1
+            WHERE card_hash = ?
+            "#,
+        )
+        .bind(result.stability)
+        .bind(result.difficulty)
+        .bind(result.interval_raw)
+        .bind(result.interval_days)
+        .bind(due_date)
+        .bind(reviewed_at.to_rfc3339())
+        .bind(&card.card_hash)
+        .execute(&mut *tx)
+        .await?;
+
+        let record_id = uuid::Uuid::new_v4().to_string();
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (
+                record_id,
+                card_hash,
+                reviewed_at,
+                grade,
+                elapsed_days,
+                stability_before,
+                stability_after,
+                difficulty_before,
+                difficulty_after,
+                scheduled_interval
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&record_id)
+        .bind(&card.card_hash)
+        .bind(reviewed_at.to_rfc3339())
+        .bind(grade as i64)
+        .bind(elapsed_days)
+        .bind(prior.map(|s| s.stability))
+        .bind(result.stability)
+        .bind(prior.map(|s| s.difficulty))
+        .bind(result.difficulty)
+        .bind(result.interval_days)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Returns the full, append-only review history for one card, ordered
+    /// from oldest to newest.
+    pub async fn review_history(&self, card_hash: &str) -> Result<Vec<ReviewLog>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, record_id, card_hash, reviewed_at, grade, elapsed_days,
+                   stability_before, stability_after,
+                   difficulty_before, difficulty_after, scheduled_interval
+            FROM review_log
+            WHERE card_hash = ?
+            ORDER BY reviewed_at ASC
+            "#,
+        )
+        .bind(card_hash)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(row_to_review_log).collect()
+    }
+
+    /// Returns every review logged strictly after `since`, ordered from
+    /// oldest to newest, for building a recall matrix or daily review
+    /// summaries.
+    ///
+    /// The bound is exclusive so `DB::sync` can pass the `reviewed_at` of
+    /// the last record it processed as the next call's `since` without
+    /// that same record being returned (and re-uploaded) again.
+    pub async fn all_reviews_since(&self, since: DateTime<Utc>) -> Result<Vec<ReviewLog>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, record_id, card_hash, reviewed_at, grade, elapsed_days,
+                   stability_before, stability_after,
+                   difficulty_before, difficulty_after, scheduled_interval
+            FROM review_log
+            WHERE reviewed_at > ?
+            ORDER BY reviewed_at ASC
+            "#,
+        )
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(row_to_review_log).collect()
+    }
+
+    /// Uploads review records appended locally since this host's last sync
+    /// position, downloads records appended by other hosts, and replays the
+    /// downloaded ones to converge each affected card's scheduler state.
+    ///
+    /// Replay is ordered by `reviewed_at` and idempotent on `record_id`, so
+    /// running `sync` repeatedly, or out of order relative to other hosts,
+    /// is always safe.
+    pub async fn sync(&self, remote: &RemoteClient) -> Result<SyncStats> {
+        let host_id = load_or_create_host_id(&self.data_dir)?;
+        let key = load_or_create_sync_key(&self.data_dir)?;
+
+        let last_position: Option<String> =
+            sqlx::query_as("SELECT last_synced_position FROM sync_state WHERE host_id = ?")
+                .bind(&host_id)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|(position,): (String,)| position);
+
+        let since = match &last_position {
+            Some(position) if !position.is_empty() => {
+                DateTime::parse_from_rfc3339(position)?.with_timezone(&Utc)
+            }
+            _ => DateTime::<Utc>::from_timestamp(0, 0).expect("epoch is a valid timestamp"),
+        };
+
+        let pending = self.all_reviews_since(since).await?;
+        let to_upload = pending
+            .iter()
+            .map(|log| encrypt_record(log, &host_id, &key))
+            .collect::<Result<Vec<_>>>()?;
+        if !to_upload.is_empty() {
+            remote.push(&to_upload).await?;
+        }
+
+        let remote_records = remote.pull(last_position.as_deref().unwrap_or("")).await?;
+        let mut downloaded = 0;
+        for record in &remote_records {
+            if record.host_id == host_id {
+                continue;
+            }
+            let log = decrypt_record(record, &key)?;
+            if self.replay_review(&log).await? {
+                downloaded += 1;
+            }
+        }
+
+        let new_position = pending
+            .iter()
+            .map(|log| log.reviewed_at)
+            .chain(remote_records.iter().map(|r| r.reviewed_at))
+            .max()
+            .map(|dt| dt.to_rfc3339())
+            .or(last_position)
+            .unwrap_or_default();
+
+        sqlx::query(
+            r#"
+            INSERT INTO sync_state (host_id, last_synced_position)
+            VALUES (?, ?)
+            ON CONFLICT(host_id) DO UPDATE SET last_synced_position = excluded.last_synced_position
+            "#,
+        )
+        .bind(&host_id)
+        .bind(new_position)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(SyncStats {
+            uploaded: to_upload.len(),
+            downloaded,
+        })
+    }
+
+    /// Replays one remote `ReviewLog` into local state. Returns `false`
+    /// without writing anything if `log.record_id` has already been
+    /// applied.
+    ///
+    /// Inserting the row alone isn't enough to converge the card: the
+    /// incoming row's `stability_after`/`difficulty_after` were computed by
+    /// the authoring device against *its own* view of prior state, which
+    /// may since have been superseded by a review from another device (two
+    /// devices independently reviewing the same card while both offline,
+    /// for instance). So after merging the row in, the card's current state
+    /// is rebuilt from scratch by re-running `schedule()` over its full
+    /// `reviewed_at`-ordered history, not by trusting whichever row happens
+    /// to have the latest timestamp.
+    async fn replay_review(&self, log: &ReviewLog) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let already_applied: (i64,) =
+            sqlx::query_as("SELECT COUNT(1) FROM review_log WHERE record_id = ?")
+                .bind(&log.record_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        if already_applied.0 > 0 {
+            tx.commit().await?;
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (
+                record_id,
+                card_hash,
+                reviewed_at,
+                grade,
+                elapsed_days,
+                stability_before,
+                stability_after,
+                difficulty_before,
+                difficulty_after,
+                scheduled_interval
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&log.record_id)
+        .bind(&log.card_hash)
+        .bind(log.reviewed_at.to_rfc3339())
+        .bind(log.grade as i64)
+        .bind(log.elapsed_days)
+        .bind(log.stability_before)
+        .bind(log.stability_after)
+        .bind(log.difficulty_before)
+        .bind(log.difficulty_after)
+        .bind(log.scheduled_interval)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.rebuild_card_state(&log.card_hash).await?;
+        Ok(true)
+    }
+
+    /// Recomputes `card_hash`'s current stability/difficulty/due date by
+    /// re-running `schedule()` sequentially over its full `review_log`
+    /// history, oldest to newest, threading `SchedulerState` forward rather
+    /// than trusting any single row's precomputed `stability_after`/
+    /// `difficulty_after` (which reflect only the writing device's view of
+    /// prior state at the time).
+    async fn rebuild_card_state(&self, card_hash: &str) -> Result<()> {
+        let history = self.review_history(card_hash).await?;
+        let Some(last) = history.last() else {
+            return Ok(());
+        };
+
+        let params = self.active_fsrs_params().await?;
+        let mut state: Option<SchedulerState> = None;
+        let mut prev_reviewed_at: Option<DateTime<Utc>> = None;
+        let mut result = None;
+
+        for entry in &history {
+            let elapsed_days = match prev_reviewed_at {
+                Some(prev) => (entry.reviewed_at - prev).num_seconds() as f64 / 86_400.0,
+                None => 0.0,
+            };
+
+            let step = schedule(state, entry.grade, elapsed_days, &params)?;
+            state = Some(SchedulerState {
+                stability: step.stability,
+                difficulty: step.difficulty,
+            });
+            prev_reviewed_at = Some(entry.reviewed_at);
+            result = Some(step);
+        }
+
+        let result = result.expect("history is non-empty");
+        let due_date = (last.reviewed_at + chrono::Duration::days(result.interval_days)).date_naive();
+
+        sqlx::query(
+            r#"
+            UPDATE cards
+            SET stability = ?,
+                difficulty = ?,
+                interval_raw = ?,
+                interval_days = ?,
+                due_date = ?,
+                last_reviewed_at = ?,
+                review_count = ?
+            WHERE card_hash = ?
+            "#,
+        )
+        .bind(result.stability)
+        .bind(result.difficulty)
+        .bind(result.interval_raw)
+        .bind(result.interval_days)
+        .bind(due_date)
+        .bind(last.reviewed_at.to_rfc3339())
+        .bind(history.len() as i64)
+        .bind(card_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Reads one value from the namespaced `kv` store.
+    pub async fn kv_get(&self, namespace: &str, key: &str) -> Result<Option<String>> {
+        let value: Option<(String,)> =
+            sqlx::query_as("SELECT value FROM kv WHERE namespace = ? AND key = ?")
+                .bind(namespace)
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(value.map(|(value,)| value))
+    }
+
+    /// Writes one value into the namespaced `kv` store, overwriting any
+    /// existing value for the same `(namespace, key)`.
+    pub async fn kv_set(&self, namespace: &str, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO kv (namespace, key, value, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(namespace, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(namespace)
+        .bind(key)
+        .bind(value)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lists every key/value pair in `namespace`.
+    pub async fn kv_list(&self, namespace: &str) -> Result<Vec<(String, String)>> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT key, value FROM kv WHERE namespace = ?")
+                .bind(namespace)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows)
+    }
+
+    /// Returns the active FSRS parameters, as persisted by
+    /// `set_fsrs_params`, falling back to the built-in defaults if none
+    /// have been saved yet.
+    pub async fn active_fsrs_params(&self) -> Result<FsrsParams> {
+        match self.kv_get("fsrs", "params").await? {
+            Some(value) => Ok(serde_json::from_str(&value)?),
+            None => Ok(FsrsParams::default()),
+        }
+    }
+
+    /// Persists `params` as the active FSRS parameter set, e.g. after
+    /// re-fitting weights against `all_reviews_since` history.
+    pub async fn set_fsrs_params(&self, params: &FsrsParams) -> Result<()> {
+        self.kv_set("fsrs", "params", &serde_json::to_string(params)?)
+            .await
+    }
+}
+
+fn row_to_review_log(row: sqlx::sqlite::SqliteRow) -> Result<ReviewLog> {
+    let reviewed_at: String = row.get("reviewed_at");
+    let grade: i64 = row.get("grade");
+
+    Ok(ReviewLog {
+        id: row.get("id"),
+        record_id: row.get("record_id"),
+        card_hash: row.get("card_hash"),
+        reviewed_at: DateTime::parse_from_rfc3339(&reviewed_at)?.with_timezone(&Utc),
+        grade: grade as u8,
+        elapsed_days: row.get("elapsed_days"),
+        stability_before: row.get("stability_before"),
+        stability_after: row.get("stability_after"),
+        difficulty_before: row.get("difficulty_before"),
+        difficulty_after: row.get("difficulty_after"),
+        scheduled_interval: row.get("scheduled_interval"),
+    })
+}
+
+#[cfg(test)]
+impl DB {
+    /// An isolated, migrated database for tests: an in-memory SQLite pool
+    /// pinned to a single connection (so the in-memory database isn't
+    /// dropped between queries) plus a scratch data directory for the
+    /// host id/sync key files.
+    async fn new_in_memory() -> Result<Self> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        run_migrations(&pool).await?;
+
+        let data_dir = std::env::temp_dir().join(format!("repeat-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir)?;
+
+        Ok(Self { pool, data_dir })
+    }
 }
 
-async fn probe_schema_exists(pool: &SqlitePool) -> Result<bool, sqlx::Error> {
-    let sql = "select count(*) from sqlite_master where type='table' AND name=?;";
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn due_today_handles_decks_larger_than_sqlites_bind_limit() -> Result<()> {
+        let db = DB::new_in_memory().await?;
+
+        let cards: Vec<Card> = (0..2_000)
+            .map(|i| Card {
+                card_hash: format!("card-{i}"),
+                front: format!("q{i}"),
+                back: format!("a{i}"),
+            })
+            .collect();
+        db.add_cards_batch(&cards).await?;
+
+        let card_hashes: HashMap<String, Card> = cards
+            .into_iter()
+            .map(|card| (card.card_hash.clone(), card))
+            .collect();
+
+        // New cards have a NULL due_date, so they're all due; a
+        // 2000-placeholder `card_hash IN (?, ?, ...)` clause would be well
+        // past SQLite's default 999 bound-parameter limit.
+        let due = db.due_today(&card_hashes, None).await?;
+        assert_eq!(due.len(), 2_000);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_host_reviews_converge_via_sequential_replay() -> Result<()> {
+        let db = DB::new_in_memory().await?;
+        let card = Card {
+            card_hash: "card-1".to_string(),
+            front: "q".to_string(),
+            back: "a".to_string(),
+        };
+        db.add_card(&card).await?;
 
-    let count: (i64,) = sqlx::query_as(sql).bind("cards").fetch_one(pool).await?;
-    Ok(count.0 > 0)
+        let params = FsrsParams::default();
+        let t1 = Utc.with_ymd_and_hms(2026, 7, 20, 9, 0, 0).unwrap();
+        let t2 = Utc.with_ymd_and_hms(2026, 7, 20, 18, 0, 0).unwrap();
+
+        // Phone reviews the card at t1 (its first-ever review).
+        let phone_result = schedule(None, 3, 0.0, &params)?;
+        let phone_log = ReviewLog {
+            id: 0,
+            record_id: "phone-1".to_string(),
+            card_hash: card.card_hash.clone(),
+            reviewed_at: t1,
+            grade: 3,
+            elapsed_days: 0.0,
+            stability_before: None,
+            stability_after: phone_result.stability,
+            difficulty_before: None,
+            difficulty_after: phone_result.difficulty,
+            scheduled_interval: phone_result.interval_days,
+        };
+
+        // Laptop, still unsynced and so unaware of the phone's review,
+        // independently reviews the same card at t2 > t1 as if it were
+        // also a first-ever review.
+        let laptop_result = schedule(None, 4, 0.0, &params)?;
+        let laptop_log = ReviewLog {
+            id: 0,
+            record_id: "laptop-1".to_string(),
+            card_hash: card.card_hash.clone(),
+            reviewed_at: t2,
+            grade: 4,
+            elapsed_days: 0.0,
+            stability_before: None,
+            stability_after: laptop_result.stability,
+            difficulty_before: None,
+            difficulty_after: laptop_result.difficulty,
+            scheduled_interval: laptop_result.interval_days,
+        };
+
+        // A third host syncs and sees the laptop's record before the
+        // phone's, exercising out-of-wire-order replay.
+        db.replay_review(&laptop_log).await?;
+        db.replay_review(&phone_log).await?;
+
+        // The correct merged state re-runs schedule() in reviewed_at order:
+        // t1 is a first-ever review, and t2 follows from t1's result — it
+        // is not a second, independent first-ever review.
+        let after_t1 = schedule(None, 3, 0.0, &params)?;
+        let after_t2 = schedule(
+            Some(SchedulerState {
+                stability: after_t1.stability,
+                difficulty: after_t1.difficulty,
+            }),
+            4,
+            (t2 - t1).num_seconds() as f64 / 86_400.0,
+            &params,
+        )?;
+
+        let row = sqlx::query("SELECT stability, difficulty FROM cards WHERE card_hash = ?")
+            .bind(&card.card_hash)
+            .fetch_one(&db.pool)
+            .await?;
+        let stability: f64 = row.get("stability");
+        let difficulty: f64 = row.get("difficulty");
+
+        assert!((stability - after_t2.stability).abs() < 1e-9);
+        assert!((difficulty - after_t2.difficulty).abs() < 1e-9);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,189 @@
+//! Free Spaced Repetition Scheduler (FSRS).
+//!
+//! Implements the FSRS v4 update rules used to turn a review grade into a
+//! new stability/difficulty pair and the resulting next-review interval.
+//! See https://github.com/open-spaced-repetition/fsrs4anki for the reference
+//! algorithm this mirrors.
+
+use anyhow::{anyhow, Result};
+
+/// Default weight vector `w`, as published by the FSRS project.
+pub const DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4, 0.6, 2.4, 5.8, 4.93, 0.94, 0.86, 0.01, 1.49, 0.14, 0.94, 2.18, 0.05, 0.34, 1.26, 0.29,
+    2.61,
+];
+
+/// Default target retention used to convert stability into an interval.
+pub const DEFAULT_REQUEST_RETENTION: f64 = 0.9;
+
+/// Tunable FSRS parameters: the weight vector and target retention.
+///
+/// Serializable so the active set can be persisted in and reloaded from
+/// the `kv` store (namespace `"fsrs"`) after re-fitting against review
+/// history.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FsrsParams {
+    pub weights: [f64; 17],
+    pub request_retention: f64,
+}
+
+impl Default for FsrsParams {
+    fn default() -> Self {
+        Self {
+            weights: DEFAULT_WEIGHTS,
+            request_retention: DEFAULT_REQUEST_RETENTION,
+        }
+    }
+}
+
+/// The scheduler state carried forward from one review to the next.
+#[derive(Debug, Clone, Copy)]
+pub struct SchedulerState {
+    pub stability: f64,
+    pub difficulty: f64,
+}
+
+/// The outcome of scheduling a single review.
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduleResult {
+    pub stability: f64,
+    pub difficulty: f64,
+    pub interval_raw: f64,
+    pub interval_days: i64,
+}
+
+fn clamp_difficulty(d: f64) -> f64 {
+    d.clamp(1.0, 10.0)
+}
+
+/// Computes the next scheduler state for a review graded `1..=4`
+/// (Again/Hard/Good/Easy).
+///
+/// `prior` is `None` on a card's first review, in which case `elapsed_days`
+/// is ignored. Otherwise `elapsed_days` is the time since the prior review
+/// that produced `prior`.
+///
+/// Returns an error if `grade` is outside `1..=4` rather than panicking, so
+/// callers other than `record_review` (e.g. weight re-fitting code walking
+/// `all_reviews_since` history) can't trigger a panic from bad input.
+pub fn schedule(
+    prior: Option<SchedulerState>,
+    grade: u8,
+    elapsed_days: f64,
+    params: &FsrsParams,
+) -> Result<ScheduleResult> {
+    if !(1..=4).contains(&grade) {
+        return Err(anyhow!("grade must be in 1..=4, got {}", grade));
+    }
+
+    let w = &params.weights;
+    let grade_offset = grade as f64 - 3.0;
+
+    let (difficulty, stability) = match prior {
+        None => {
+            let difficulty = clamp_difficulty(w[4] - grade_offset * w[5]);
+            let stability = w[(grade - 1) as usize];
+            (difficulty, stability)
+        }
+        Some(state) => {
+            let r = (1.0 + elapsed_days / (9.0 * state.stability)).powf(-1.0);
+            let difficulty =
+                clamp_difficulty(w[7] * w[4] + (1.0 - w[7]) * (state.difficulty - w[6] * grade_offset));
+
+            let stability = if grade == 1 {
+                w[11]
+                    * difficulty.powf(-w[12])
+                    * ((state.stability + 1.0).powf(w[13]) - 1.0)
+                    * ((1.0 - r) * w[14]).exp()
+            } else {
+                let hard_penalty = if grade == 2 { w[15] } else { 1.0 };
+                let easy_bonus = if grade == 4 { w[16] } else { 1.0 };
+                state.stability
+                    * (1.0
+                        + w[8].exp()
+                            * (11.0 - difficulty)
+                            * state.stability.powf(-w[9])
+                            * (((1.0 - r) * w[10]).exp() - 1.0)
+                            * hard_penalty
+                            * easy_bonus)
+            };
+            (difficulty, stability)
+        }
+    };
+
+    let interval_raw = (9.0 * stability) * (1.0 / params.request_retention - 1.0);
+    let interval_days = interval_raw.round().max(1.0) as i64;
+
+    Ok(ScheduleResult {
+        stability,
+        difficulty,
+        interval_raw,
+        interval_days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_review_uses_initial_difficulty_and_stability() {
+        let params = FsrsParams::default();
+        let result = schedule(None, 3, 0.0, &params).unwrap();
+
+        assert_eq!(result.stability, params.weights[2]);
+        assert_eq!(
+            result.difficulty,
+            clamp_difficulty(params.weights[4] - 0.0 * params.weights[5])
+        );
+    }
+
+    #[test]
+    fn lapse_shrinks_stability_relative_to_a_successful_recall() {
+        let params = FsrsParams::default();
+        let prior = SchedulerState {
+            stability: 10.0,
+            difficulty: 5.0,
+        };
+
+        let again = schedule(Some(prior), 1, 5.0, &params).unwrap();
+        let good = schedule(Some(prior), 3, 5.0, &params).unwrap();
+
+        assert!(again.stability < good.stability);
+    }
+
+    #[test]
+    fn interval_grows_with_stability() {
+        let params = FsrsParams::default();
+        let low = schedule(
+            Some(SchedulerState {
+                stability: 2.0,
+                difficulty: 5.0,
+            }),
+            3,
+            2.0,
+            &params,
+        )
+        .unwrap();
+        let high = schedule(
+            Some(SchedulerState {
+                stability: 20.0,
+                difficulty: 5.0,
+            }),
+            3,
+            2.0,
+            &params,
+        )
+        .unwrap();
+
+        assert!(high.interval_days > low.interval_days);
+    }
+
+    #[test]
+    fn grade_out_of_range_is_rejected_instead_of_panicking() {
+        let params = FsrsParams::default();
+
+        assert!(schedule(None, 0, 0.0, &params).is_err());
+        assert!(schedule(None, 5, 0.0, &params).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+//! Versioned schema migrations.
+//!
+//! Each migration is a plain SQL string, embedded at compile time and
+//! applied in its own transaction. The database's `user_version` pragma
+//! records how many have been applied, so old and new installs converge on
+//! the same schema and a migration never runs twice.
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// Ordered, embedded up-migrations. Append new migrations to the end;
+/// never edit or reorder an entry once it has shipped, since `user_version`
+/// is just its index.
+const MIGRATIONS: &[&str] = &[
+    include_str!("schema.sql"),
+    include_str!("schema_0002_sync.sql"),
+    include_str!("schema_0003_kv.sql"),
+];
+
+/// Applies every migration after the database's current `user_version`,
+/// each in its own transaction, rolling back and bailing out on the first
+/// failure so a database never ends up on a half-applied migration.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+    let (current,): (i64,) = sqlx::query_as("PRAGMA user_version;").fetch_one(pool).await?;
+    let current = current as usize;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let mut tx = pool.begin().await?;
+        sqlx::query(migration).execute(&mut *tx).await?;
+        sqlx::query(&format!("PRAGMA user_version = {};", i + 1))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A single, append-only record of one review, as written by
+/// `DB::record_review`.
+///
+/// Unlike the `cards` table (which only ever holds the *current* scheduler
+/// state), `review_log` rows are never updated or deleted, which is what
+/// lets stats and FSRS weight re-fitting work from real review history.
+///
+/// `record_id` is a stable, globally-unique id assigned at write time
+/// (independent of the local autoincrement `id`), so the record keeps its
+/// identity across devices when synced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewLog {
+    pub id: i64,
+    pub record_id: String,
+    pub card_hash: String,
+    pub reviewed_at: DateTime<Utc>,
+    pub grade: u8,
+    pub elapsed_days: f64,
+    pub stability_before: Option<f64>,
+    pub stability_after: f64,
+    pub difficulty_before: Option<f64>,
+    pub difficulty_after: f64,
+    pub scheduled_interval: i64,
+}
@@ -0,0 +1,208 @@
+//! Encrypted multi-device sync of review history.
+//!
+//! Mirrors the model used by encrypted shell-history sync tools: every
+//! `review_log` row carries a stable `record_id` assigned at write time, and
+//! syncing uploads locally-appended records, downloads records other hosts
+//! have appended, and replays them deterministically (ordered by
+//! `reviewed_at`, idempotent on `record_id`) to rebuild each card's current
+//! scheduler state. Record payloads are encrypted client-side before
+//! upload, so the remote store only ever sees opaque blobs.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+use crate::review_log::ReviewLog;
+
+/// A review record as it travels over the wire: encrypted and tagged with
+/// the host that authored it, so a host can skip replaying its own writes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedRecord {
+    pub record_id: String,
+    pub host_id: String,
+    pub reviewed_at: DateTime<Utc>,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Outcome of one `DB::sync` call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    pub uploaded: usize,
+    pub downloaded: usize,
+}
+
+/// A remote record store. Implementations only ever see opaque
+/// `EncryptedRecord`s, never plaintext review data.
+pub struct RemoteClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl RemoteClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub(crate) async fn push(&self, records: &[EncryptedRecord]) -> Result<()> {
+        self.http
+            .post(format!("{}/records", self.base_url))
+            .json(records)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    pub(crate) async fn pull(&self, since_position: &str) -> Result<Vec<EncryptedRecord>> {
+        let records = self
+            .http
+            .get(format!("{}/records", self.base_url))
+            .query(&[("since", since_position)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<EncryptedRecord>>()
+            .await?;
+        Ok(records)
+    }
+}
+
+/// Writes `contents` to `path`, creating the file with permissions
+/// restricted to the owner (`0600` on Unix) so the sync key and host id
+/// aren't left group/world-readable on a shared machine — the whole point
+/// of client-side encryption is that only this install can read them.
+#[cfg(unix)]
+fn write_private_file(path: &std::path::Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_private_file(path: &std::path::Path, contents: &[u8]) -> Result<()> {
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Loads this install's stable host id from the data directory, creating
+/// one on first run.
+pub fn load_or_create_host_id(data_dir: &Path) -> Result<String> {
+    let path = data_dir.join("host_id");
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        return Ok(existing.trim().to_string());
+    }
+
+    let host_id = uuid::Uuid::new_v4().to_string();
+    write_private_file(&path, host_id.as_bytes())?;
+    Ok(host_id)
+}
+
+/// Loads this install's sync encryption key from the data directory,
+/// creating one on first run.
+pub fn load_or_create_sync_key(data_dir: &Path) -> Result<[u8; 32]> {
+    let path = data_dir.join("sync.key");
+    if let Ok(existing) = std::fs::read(&path) {
+        return existing
+            .try_into()
+            .map_err(|_| anyhow!("sync.key at {} is corrupt: expected 32 bytes", path.display()));
+    }
+
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    write_private_file(&path, &key)?;
+    Ok(key)
+}
+
+/// Encrypts `log` into the wire format uploaded to the remote store.
+pub fn encrypt_record(log: &ReviewLog, host_id: &str, key: &[u8; 32]) -> Result<EncryptedRecord> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+
+    let mut nonce_bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let plaintext = serde_json::to_vec(log)?;
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_ref())
+        .map_err(|e| anyhow!("failed to encrypt review record: {}", e))?;
+
+    Ok(EncryptedRecord {
+        record_id: log.record_id.clone(),
+        host_id: host_id.to_string(),
+        reviewed_at: log.reviewed_at,
+        nonce: nonce_bytes.to_vec(),
+        ciphertext,
+    })
+}
+
+/// Decrypts a record pulled from the remote store back into a `ReviewLog`.
+pub fn decrypt_record(record: &EncryptedRecord, key: &[u8; 32]) -> Result<ReviewLog> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Nonce::from_slice(&record.nonce);
+
+    let plaintext = cipher
+        .decrypt(nonce, record.ciphertext.as_ref())
+        .map_err(|e| anyhow!("failed to decrypt review record {}: {}", record.record_id, e))?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample_log() -> ReviewLog {
+        ReviewLog {
+            id: 1,
+            record_id: "rec-1".to_string(),
+            card_hash: "card-1".to_string(),
+            reviewed_at: Utc.with_ymd_and_hms(2026, 7, 20, 9, 0, 0).unwrap(),
+            grade: 3,
+            elapsed_days: 0.0,
+            stability_before: None,
+            stability_after: 2.4,
+            difficulty_before: None,
+            difficulty_after: 4.93,
+            scheduled_interval: 3,
+        }
+    }
+
+    #[test]
+    fn encrypt_then_decrypt_round_trips_to_the_original_record() {
+        let key = [7u8; 32];
+        let log = sample_log();
+
+        let record = encrypt_record(&log, "host-a", &key).unwrap();
+        assert_eq!(record.record_id, log.record_id);
+        assert_eq!(record.host_id, "host-a");
+
+        let decrypted = decrypt_record(&record, &key).unwrap();
+        assert_eq!(decrypted, log);
+    }
+
+    #[test]
+    fn decrypt_fails_with_the_wrong_key() {
+        let log = sample_log();
+        let record = encrypt_record(&log, "host-a", &[1u8; 32]).unwrap();
+
+        assert!(decrypt_record(&record, &[2u8; 32]).is_err());
+    }
+}